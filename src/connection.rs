@@ -0,0 +1,55 @@
+//! Connection plumbing for the optional Postgres/MySQL backends. SQLite
+//! stays the default; enabling the `postgres` or `mysql` Cargo feature
+//! compiles in that backend's connection variant and `schema` module, and
+//! replaces SQLite only when default features are disabled
+//! (`--no-default-features`). Model structs in `models` derive `Identifiable`/`Associations`
+//! against a single backend's table definitions, so exactly one of
+//! `sqlite`, `postgres`, `mysql` should be enabled for a given build.
+
+/// Declares `DbConnection`, an enum with one variant per backend enabled via
+/// Cargo features, each wrapping that backend's Diesel connection type.
+macro_rules! generate_connection {
+    () => {
+        pub enum DbConnection {
+            #[cfg(feature = "sqlite")]
+            Sqlite(diesel::sqlite::SqliteConnection),
+            #[cfg(feature = "postgres")]
+            Postgres(diesel::pg::PgConnection),
+            #[cfg(feature = "mysql")]
+            Mysql(diesel::mysql::MysqlConnection),
+        }
+    };
+}
+
+generate_connection!();
+
+/// Runs `$body` against whichever backend `$conn` currently holds. The
+/// `|$conn_name| $body` form names the concrete connection `$body` should
+/// use (conventionally the same name as `$conn` itself, shadowed) — a plain
+/// `$body:expr` can't do this, since macro hygiene keeps a `conn` bound
+/// inside the macro's own match arms distinct from the `conn` written at
+/// the call site. Each arm also brings that backend's `schema` module into
+/// scope, so `$body` can reference `posts`, `users`, etc. exactly as it
+/// would for a single-backend crate.
+#[macro_export]
+macro_rules! db_run {
+    ($conn:expr, |$conn_name:ident| $body:expr) => {
+        match $conn {
+            #[cfg(feature = "sqlite")]
+            $crate::connection::DbConnection::Sqlite($conn_name) => {
+                use $crate::schema::sqlite::*;
+                $body
+            }
+            #[cfg(feature = "postgres")]
+            $crate::connection::DbConnection::Postgres($conn_name) => {
+                use $crate::schema::postgres::*;
+                $body
+            }
+            #[cfg(feature = "mysql")]
+            $crate::connection::DbConnection::Mysql($conn_name) => {
+                use $crate::schema::mysql::*;
+                $body
+            }
+        }
+    };
+}
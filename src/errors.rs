@@ -0,0 +1,30 @@
+use diesel::result::Error as DieselError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound,
+    InvalidInput(String),
+    Internal(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound => write!(f, "not found"),
+            AppError::InvalidInput(msg) => write!(f, "{}", msg),
+            AppError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<DieselError> for AppError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::NotFound => AppError::NotFound,
+            err => AppError::Internal(err.to_string()),
+        }
+    }
+}
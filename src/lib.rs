@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate serde_derive;
+
+pub mod connection;
+pub mod errors;
+pub mod models;
+pub mod schema;
@@ -1,11 +1,82 @@
+use crate::connection::DbConnection;
+use crate::db_run;
 use crate::errors::AppError;
-use crate::schema::comments;
-use crate::schema::posts;
-use crate::schema::users;
+use diesel::dsl::sql;
 use diesel::prelude::*;
+use diesel::sql_types::Integer;
+
+// Only the tables referenced by a `#[derive(Identifiable, Associations)]` or
+// `#[table_name = "..."]` struct below need to be in scope at module level;
+// every other table is brought into scope per-call by `db_run!`'s glob
+// import of the active backend's `schema` module.
+#[cfg(feature = "sqlite")]
+use crate::schema::sqlite::{comments, post_aggregates, post_like, post_report, posts, users};
+#[cfg(feature = "postgres")]
+use crate::schema::postgres::{comments, post_aggregates, post_like, post_report, posts, users};
+#[cfg(feature = "mysql")]
+use crate::schema::mysql::{comments, post_aggregates, post_like, post_report, posts, users};
 
 type Result<T> = std::result::Result<T, AppError>;
 
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 50;
+
+/// Ordering requested for a listing query.
+pub enum SortType {
+    /// Newest first (by id, descending).
+    New,
+    /// Oldest first (by id, ascending).
+    Old,
+    /// Posts with the most recent comment activity first.
+    Active,
+}
+
+/// Clamps `limit` to `1..=MAX_LIMIT` (default `DEFAULT_LIMIT`) and turns a
+/// 1-indexed `page` into a `(limit, offset)` pair ready for `.limit`/`.offset`.
+pub fn limit_and_offset(page: Option<i64>, limit: Option<i64>) -> (i64, i64) {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let page = page.unwrap_or(1).max(1);
+    let offset = (page - 1) * limit;
+    (limit, offset)
+}
+
+/// `LIKE` is case-insensitive by default on SQLite and MySQL, but
+/// case-sensitive on Postgres, so a plain `.like()` would silently change
+/// behavior across backends. On Postgres use `.ilike()`
+/// (`PgTextExpressionMethods`) instead; elsewhere `.like()` already does
+/// the right thing.
+#[cfg(feature = "postgres")]
+use diesel::expression_methods::PgTextExpressionMethods;
+
+/// Case-insensitive substring match, uniform across backends: `.ilike()` on
+/// Postgres (whose `ILIKE` already escapes with `\` by default, so no
+/// `.escape()` call is needed there), plain `.like().escape('\\')`
+/// everywhere else. See the `PgTextExpressionMethods` import above for why
+/// this can't just be a runtime branch.
+macro_rules! case_insensitive_like {
+    ($column:expr, $pattern:expr) => {{
+        #[cfg(feature = "postgres")]
+        {
+            $column.ilike($pattern)
+        }
+        #[cfg(not(feature = "postgres"))]
+        {
+            $column.like($pattern).escape('\\')
+        }
+    }};
+}
+
+/// Escapes `\`, `%`, and `_` in `term` so user input can't inject `LIKE`
+/// wildcards, then wraps it as `%term%` for a case-insensitive substring
+/// match (paired with `.escape('\\')` at the call site).
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
 #[derive(Queryable, Identifiable, Serialize, Debug, PartialEq)]
 pub struct User {
     pub id: i32,
@@ -44,170 +115,755 @@ pub struct PostWithComment {
     pub published: bool,
 }
 
-pub fn create_user(conn: &SqliteConnection, username: &str) -> Result<User> {
-    conn.transaction(|| {
-        diesel::insert_into(users::table)
-            .values((users::username.eq(username),))
-            .execute(conn)?;
+#[derive(Queryable, Identifiable, Associations, Serialize, Debug)]
+#[belongs_to(Post)]
+#[primary_key(post_id)]
+#[table_name = "post_aggregates"]
+pub struct PostAggregates {
+    pub post_id: i32,
+    pub comments: i32,
+    pub score: i32,
+    pub newest_comment_time: Option<chrono::NaiveDateTime>,
+}
 
-        users::table
-            .order(users::id.desc())
-            .select((users::id, users::username))
-            .first(conn)
+#[derive(Queryable, Identifiable, Associations, Serialize, Debug)]
+#[belongs_to(User)]
+#[belongs_to(Post)]
+#[primary_key(user_id, post_id)]
+#[table_name = "post_like"]
+pub struct PostLike {
+    pub user_id: i32,
+    pub post_id: i32,
+    pub score: i16,
+}
+
+#[derive(Queryable, Identifiable, Associations, Serialize, Debug)]
+#[belongs_to(Post)]
+#[table_name = "post_report"]
+pub struct PostReport {
+    pub id: i32,
+    pub creator_id: i32,
+    pub post_id: i32,
+    pub reason: String,
+    pub resolved: bool,
+    pub resolver_id: Option<i32>,
+}
+
+/// A report joined with the post it targets, the user who filed it, the
+/// post's author, and whoever resolved it (if anyone yet has). Needs three
+/// distinct aliases of `users`, since all three roles can be different rows
+/// of the same table in a single query.
+#[derive(Serialize, Debug)]
+pub struct PostReportView {
+    pub report: PostReport,
+    pub post: Post,
+    pub creator: User,
+    pub post_author: User,
+    pub resolver: Option<User>,
+}
+
+pub fn create_user(conn: &DbConnection, username: &str) -> Result<User> {
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::insert_into(users::table)
+                .values((users::username.eq(username),))
+                .execute(conn)?;
+
+            users::table
+                .order(users::id.desc())
+                .select((users::id, users::username))
+                .first(conn)
+                .map_err(Into::into)
+        })
+    })
+}
+
+pub fn find_user<'a>(conn: &DbConnection, key: UserKey<'a>) -> Result<User> {
+    db_run!(conn, |conn| {
+        match key {
+            UserKey::Username(name) => users::table
+                .filter(users::username.eq(name))
+                .select((users::id, users::username))
+                .first::<User>(conn)
+                .map_err(AppError::from),
+            UserKey::ID(id) => users::table
+                .find(id)
+                .select((users::id, users::username))
+                .first::<User>(conn)
+                .map_err(Into::into),
+        }
+    })
+}
+
+pub fn create_post(conn: &DbConnection, user: &User, title: &str, body: &str) -> Result<Post> {
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::insert_into(posts::table)
+                .values((
+                    posts::user_id.eq(user.id),
+                    posts::title.eq(title),
+                    posts::body.eq(body),
+                ))
+                .execute(conn)?;
+
+            let post: Post = posts::table
+                .order(posts::id.desc())
+                .select(posts::all_columns)
+                .first(conn)?;
+
+            diesel::insert_into(post_aggregates::table)
+                .values((
+                    post_aggregates::post_id.eq(post.id),
+                    post_aggregates::comments.eq(0),
+                    post_aggregates::score.eq(0),
+                ))
+                .execute(conn)?;
+
+            Ok(post)
+        })
+    })
+}
+
+pub fn publish_post(conn: &DbConnection, post_id: i32) -> Result<Post> {
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::update(posts::table.filter(posts::id.eq(post_id)))
+                .set(posts::published.eq(true))
+                .execute(conn)?;
+
+            posts::table
+                .find(post_id)
+                .select(posts::all_columns)
+                .first(conn)
+                .map_err(Into::into)
+        })
+    })
+}
+
+/// Most recently commented post id first, for `SortType::Active`.
+const ACTIVE_ORDER: &str =
+    "COALESCE((SELECT MAX(comments.id) FROM comments WHERE comments.post_id = posts.id), 0) DESC";
+
+pub fn all_posts(
+    conn: &DbConnection,
+    sort: SortType,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Post, User, PostAggregates)>> {
+    let (limit, offset) = limit_and_offset(page, limit);
+    db_run!(conn, |conn| {
+        let query = posts::table
+            .filter(posts::published.eq(true))
+            .inner_join(users::table)
+            .inner_join(post_aggregates::table)
+            .select((
+                posts::all_columns,
+                (users::id, users::username),
+                post_aggregates::all_columns,
+            ))
+            .into_boxed();
+
+        let query = match sort {
+            SortType::New => query.order(posts::id.desc()),
+            SortType::Old => query.order(posts::id.asc()),
+            SortType::Active => query.order(sql::<Integer>(ACTIVE_ORDER)),
+        };
+
+        query
+            .limit(limit)
+            .offset(offset)
+            .load::<(Post, User, PostAggregates)>(conn)
             .map_err(Into::into)
     })
 }
 
-pub fn find_user<'a>(conn: &SqliteConnection, key: UserKey<'a>) -> Result<User> {
-    match key {
-        UserKey::Username(name) => users::table
-            .filter(users::username.eq(name))
-            .select((users::id, users::username))
-            .first::<User>(conn)
-            .map_err(AppError::from),
-        UserKey::ID(id) => users::table
-            .find(id)
-            .select((users::id, users::username))
-            .first::<User>(conn)
-            .map_err(Into::into),
-    }
+pub fn user_posts(
+    conn: &DbConnection,
+    user_id: i32,
+    sort: SortType,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Post, PostAggregates)>> {
+    let (limit, offset) = limit_and_offset(page, limit);
+    db_run!(conn, |conn| {
+        let query = posts::table
+            .filter(posts::user_id.eq(user_id))
+            .inner_join(post_aggregates::table)
+            .select((posts::all_columns, post_aggregates::all_columns))
+            .into_boxed();
+
+        let query = match sort {
+            SortType::New => query.order(posts::id.desc()),
+            SortType::Old => query.order(posts::id.asc()),
+            SortType::Active => query.order(sql::<Integer>(ACTIVE_ORDER)),
+        };
+
+        query
+            .limit(limit)
+            .offset(offset)
+            .load::<(Post, PostAggregates)>(conn)
+            .map_err(Into::into)
+    })
 }
 
-pub fn create_post(conn: &SqliteConnection, user: &User, title: &str, body: &str) -> Result<Post> {
-    conn.transaction(|| {
-        diesel::insert_into(posts::table)
-            .values((
-                posts::user_id.eq(user.id),
-                posts::title.eq(title),
-                posts::body.eq(body),
+/// Shared body of `update_post_aggregates_on_comment`, factored out as a
+/// macro (rather than a plain fn taking `&DbConnection`) because
+/// `create_comment` needs to run this update inside the same `db_run!`
+/// dispatch as its insert — nesting a second `db_run!` there would re-match
+/// on an already-unwrapped concrete connection, which isn't a `DbConnection`
+/// and won't type-check. Expanding inline keeps both call sites in sync.
+macro_rules! bump_post_aggregates_on_comment {
+    ($conn:expr, $post_id:expr) => {
+        diesel::update(post_aggregates::table.filter(post_aggregates::post_id.eq($post_id)))
+            .set((
+                post_aggregates::comments.eq(post_aggregates::comments + 1),
+                post_aggregates::newest_comment_time.eq(diesel::dsl::now),
             ))
+            .execute($conn)
+    };
+}
+
+/// Increments the cached comment count for `post_id` and bumps its
+/// `newest_comment_time`, keeping `post_aggregates` in sync with `comments`.
+/// Must be called from within the same transaction as the comment insert.
+pub fn update_post_aggregates_on_comment(conn: &DbConnection, post_id: i32) -> Result<()> {
+    db_run!(conn, |conn| {
+        bump_post_aggregates_on_comment!(conn, post_id)?;
+        Ok(())
+    })
+}
+
+/// Upserts `user_id`'s vote on `post_id` to `score` (-1, 0, or 1) and
+/// recomputes `post_aggregates.score` from the full set of votes, all
+/// within a single transaction.
+pub fn like_post(conn: &DbConnection, user_id: i32, post_id: i32, score: i16) -> Result<()> {
+    if !(-1..=1).contains(&score) {
+        return Err(AppError::InvalidInput(format!(
+            "score must be -1, 0, or 1, got {}",
+            score
+        )));
+    }
+
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            let updated = diesel::update(
+                post_like::table
+                    .filter(post_like::user_id.eq(user_id))
+                    .filter(post_like::post_id.eq(post_id)),
+            )
+            .set(post_like::score.eq(score))
             .execute(conn)?;
 
-        posts::table
-            .order(posts::id.desc())
-            .select(posts::all_columns)
-            .first(conn)
+            if updated == 0 {
+                diesel::insert_into(post_like::table)
+                    .values((
+                        post_like::user_id.eq(user_id),
+                        post_like::post_id.eq(post_id),
+                        post_like::score.eq(score),
+                    ))
+                    .execute(conn)?;
+            }
+
+            let total_score: Option<i64> = post_like::table
+                .filter(post_like::post_id.eq(post_id))
+                .select(diesel::dsl::sum(post_like::score))
+                .first(conn)?;
+
+            diesel::update(post_aggregates::table.filter(post_aggregates::post_id.eq(post_id)))
+                .set(post_aggregates::score.eq(total_score.unwrap_or(0) as i32))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    })
+}
+
+/// Same shape as `all_posts`, plus `my_vote`: the caller's own vote on each
+/// post, or `None` when `my_user_id` is `None` or hasn't voted. Left-join
+/// filtering needs a concrete id, so an absent caller is joined against
+/// `-1`, an id no real user can have.
+pub fn all_posts_with_vote(
+    conn: &DbConnection,
+    my_user_id: Option<i32>,
+    sort: SortType,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Post, User, PostAggregates, Option<i16>)>> {
+    let (limit, offset) = limit_and_offset(page, limit);
+    let join_id = my_user_id.unwrap_or(-1);
+    db_run!(conn, |conn| {
+        let query = posts::table
+            .filter(posts::published.eq(true))
+            .inner_join(users::table)
+            .inner_join(post_aggregates::table)
+            .left_join(
+                post_like::table
+                    .on(post_like::post_id.eq(posts::id).and(post_like::user_id.eq(join_id))),
+            )
+            .select((
+                posts::all_columns,
+                (users::id, users::username),
+                post_aggregates::all_columns,
+                post_like::score.nullable(),
+            ))
+            .into_boxed();
+
+        let query = match sort {
+            SortType::New => query.order(posts::id.desc()),
+            SortType::Old => query.order(posts::id.asc()),
+            SortType::Active => query.order(sql::<Integer>(ACTIVE_ORDER)),
+        };
+
+        query
+            .limit(limit)
+            .offset(offset)
+            .load::<(Post, User, PostAggregates, Option<i16>)>(conn)
             .map_err(Into::into)
     })
 }
 
-pub fn publish_post(conn: &SqliteConnection, post_id: i32) -> Result<Post> {
-    conn.transaction(|| {
-        diesel::update(posts::table.filter(posts::id.eq(post_id)))
-            .set(posts::published.eq(true))
-            .execute(conn)?;
+pub fn save_post(conn: &DbConnection, user_id: i32, post_id: i32) -> Result<()> {
+    db_run!(conn, |conn| {
+        let already_saved: i64 = post_saved::table
+            .filter(post_saved::user_id.eq(user_id))
+            .filter(post_saved::post_id.eq(post_id))
+            .count()
+            .get_result(conn)?;
 
-        posts::table
-            .find(post_id)
-            .select(posts::all_columns)
-            .first(conn)
+        if already_saved == 0 {
+            diesel::insert_into(post_saved::table)
+                .values((
+                    post_saved::user_id.eq(user_id),
+                    post_saved::post_id.eq(post_id),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn unsave_post(conn: &DbConnection, user_id: i32, post_id: i32) -> Result<()> {
+    db_run!(conn, |conn| {
+        diesel::delete(
+            post_saved::table
+                .filter(post_saved::user_id.eq(user_id))
+                .filter(post_saved::post_id.eq(post_id)),
+        )
+        .execute(conn)?;
+        Ok(())
+    })
+}
+
+pub fn mark_post_read(conn: &DbConnection, user_id: i32, post_id: i32) -> Result<()> {
+    db_run!(conn, |conn| {
+        let already_read: i64 = post_read::table
+            .filter(post_read::user_id.eq(user_id))
+            .filter(post_read::post_id.eq(post_id))
+            .count()
+            .get_result(conn)?;
+
+        if already_read == 0 {
+            diesel::insert_into(post_read::table)
+                .values((
+                    post_read::user_id.eq(user_id),
+                    post_read::post_id.eq(post_id),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Same shape as `all_posts`, plus `saved`/`read` flags for `my_user_id`.
+/// As with `all_posts_with_vote`, an absent caller is joined against `-1` so
+/// every post simply comes back unsaved and unread.
+pub fn read_posts_for_user(
+    conn: &DbConnection,
+    my_user_id: Option<i32>,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Post, User, PostAggregates, bool, bool)>> {
+    let join_id = my_user_id.unwrap_or(-1);
+    let (limit, offset) = limit_and_offset(page, limit);
+    db_run!(conn, |conn| {
+        let rows = posts::table
+            .filter(posts::published.eq(true))
+            .order(posts::id.desc())
+            .inner_join(users::table)
+            .inner_join(post_aggregates::table)
+            .left_join(
+                post_saved::table
+                    .on(post_saved::post_id.eq(posts::id).and(post_saved::user_id.eq(join_id))),
+            )
+            .left_join(
+                post_read::table
+                    .on(post_read::post_id.eq(posts::id).and(post_read::user_id.eq(join_id))),
+            )
+            .select((
+                posts::all_columns,
+                (users::id, users::username),
+                post_aggregates::all_columns,
+                post_saved::user_id.nullable(),
+                post_read::user_id.nullable(),
+            ))
+            .limit(limit)
+            .offset(offset)
+            .load::<(Post, User, PostAggregates, Option<i32>, Option<i32>)>(conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(post, user, aggregates, saved, read)| {
+                (post, user, aggregates, saved.is_some(), read.is_some())
+            })
+            .collect())
+    })
+}
+
+pub fn create_report(
+    conn: &DbConnection,
+    creator_id: i32,
+    post_id: i32,
+    reason: &str,
+) -> Result<PostReport> {
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::insert_into(post_report::table)
+                .values((
+                    post_report::creator_id.eq(creator_id),
+                    post_report::post_id.eq(post_id),
+                    post_report::reason.eq(reason),
+                    post_report::resolved.eq(false),
+                ))
+                .execute(conn)?;
+
+            post_report::table
+                .order(post_report::id.desc())
+                .select(post_report::all_columns)
+                .first(conn)
+                .map_err(Into::into)
+        })
+    })
+}
+
+pub fn resolve_report(conn: &DbConnection, report_id: i32, resolver_id: i32) -> Result<PostReport> {
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::update(post_report::table.filter(post_report::id.eq(report_id)))
+                .set((
+                    post_report::resolved.eq(true),
+                    post_report::resolver_id.eq(resolver_id),
+                ))
+                .execute(conn)?;
+
+            post_report::table
+                .find(report_id)
+                .select(post_report::all_columns)
+                .first(conn)
+                .map_err(Into::into)
+        })
+    })
+}
+
+pub fn unresolved_reports(conn: &DbConnection) -> Result<Vec<PostReport>> {
+    db_run!(conn, |conn| {
+        post_report::table
+            .filter(post_report::resolved.eq(false))
+            .order(post_report::id.asc())
+            .select(post_report::all_columns)
+            .load::<PostReport>(conn)
             .map_err(Into::into)
     })
 }
 
-pub fn all_posts(conn: &SqliteConnection) -> Result<Vec<(Post, User)>> {
-    posts::table
-        .order(posts::id.desc())
-        .filter(posts::published.eq(true))
-        .inner_join(users::table)
-        .select((posts::all_columns, (users::id, users::username)))
-        .load::<(Post, User)>(conn)
-        .map_err(Into::into)
+/// Row shape for `post_report_view`'s hand-written `sql_query`. Diesel 1.4's
+/// `table!` has no table-aliasing mechanism — `#[sql_name = "..."]` only
+/// renames a single table's own SQL name (it doesn't emit `AS` in the `FROM`
+/// clause), so three `table! { #[sql_name = "users"] users_alias_N {...} }`
+/// definitions all compile to the same unaliased `FROM users`, which is
+/// ambiguous the moment more than one is joined in. Real `AS` aliases need
+/// either raw SQL or a hand-rolled `QuerySource`; raw SQL decoded through
+/// `QueryableByName` is the more direct fit here, since this is a single
+/// fixed-shape read with no query-builder composition to preserve.
+#[derive(QueryableByName, Debug)]
+struct PostReportViewRow {
+    #[sql_type = "diesel::sql_types::Integer"]
+    report_id: i32,
+    #[sql_type = "diesel::sql_types::Integer"]
+    report_creator_id: i32,
+    #[sql_type = "diesel::sql_types::Integer"]
+    report_post_id: i32,
+    #[sql_type = "diesel::sql_types::Text"]
+    report_reason: String,
+    #[sql_type = "diesel::sql_types::Bool"]
+    report_resolved: bool,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Integer>"]
+    report_resolver_id: Option<i32>,
+    #[sql_type = "diesel::sql_types::Integer"]
+    post_id: i32,
+    #[sql_type = "diesel::sql_types::Integer"]
+    post_user_id: i32,
+    #[sql_type = "diesel::sql_types::Text"]
+    post_title: String,
+    #[sql_type = "diesel::sql_types::Text"]
+    post_body: String,
+    #[sql_type = "diesel::sql_types::Bool"]
+    post_published: bool,
+    #[sql_type = "diesel::sql_types::Integer"]
+    creator_id: i32,
+    #[sql_type = "diesel::sql_types::Text"]
+    creator_username: String,
+    #[sql_type = "diesel::sql_types::Integer"]
+    post_author_id: i32,
+    #[sql_type = "diesel::sql_types::Text"]
+    post_author_username: String,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Integer>"]
+    resolver_id: Option<i32>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Text>"]
+    resolver_username: Option<String>,
 }
 
-pub fn user_posts(conn: &SqliteConnection, user_id: i32) -> Result<Vec<Post>> {
-    posts::table
-        .filter(posts::user_id.eq(user_id))
-        .order(posts::id.desc())
-        .select(posts::all_columns)
-        .load::<Post>(conn)
-        .map_err(Into::into)
+const POST_REPORT_VIEW_SELECT: &str = "
+    SELECT
+        pr.id AS report_id,
+        pr.creator_id AS report_creator_id,
+        pr.post_id AS report_post_id,
+        pr.reason AS report_reason,
+        pr.resolved AS report_resolved,
+        pr.resolver_id AS report_resolver_id,
+        p.id AS post_id,
+        p.user_id AS post_user_id,
+        p.title AS post_title,
+        p.body AS post_body,
+        p.published AS post_published,
+        creator.id AS creator_id,
+        creator.username AS creator_username,
+        post_author.id AS post_author_id,
+        post_author.username AS post_author_username,
+        resolver.id AS resolver_id,
+        resolver.username AS resolver_username
+    FROM post_report pr
+    INNER JOIN posts p ON pr.post_id = p.id
+    INNER JOIN users creator ON pr.creator_id = creator.id
+    INNER JOIN users post_author ON p.user_id = post_author.id
+    LEFT JOIN users resolver ON pr.resolver_id = resolver.id
+    WHERE pr.id = ";
+
+/// Postgres needs `$1`-style placeholders; SQLite and MySQL both take `?`.
+#[cfg(feature = "postgres")]
+const POST_REPORT_VIEW_PLACEHOLDER: &str = "$1";
+#[cfg(not(feature = "postgres"))]
+const POST_REPORT_VIEW_PLACEHOLDER: &str = "?";
+
+pub fn post_report_view(conn: &DbConnection, report_id: i32) -> Result<PostReportView> {
+    db_run!(conn, |conn| {
+        let query = format!("{}{}", POST_REPORT_VIEW_SELECT, POST_REPORT_VIEW_PLACEHOLDER);
+        let row: PostReportViewRow = diesel::sql_query(query)
+            .bind::<Integer, _>(report_id)
+            .get_result(conn)?;
+
+        let resolver = match (row.resolver_id, row.resolver_username) {
+            (Some(id), Some(username)) => Some(User { id, username }),
+            _ => None,
+        };
+
+        Ok(PostReportView {
+            report: PostReport {
+                id: row.report_id,
+                creator_id: row.report_creator_id,
+                post_id: row.report_post_id,
+                reason: row.report_reason,
+                resolved: row.report_resolved,
+                resolver_id: row.report_resolver_id,
+            },
+            post: Post {
+                id: row.post_id,
+                user_id: row.post_user_id,
+                title: row.post_title,
+                body: row.post_body,
+                published: row.post_published,
+            },
+            creator: User {
+                id: row.creator_id,
+                username: row.creator_username,
+            },
+            post_author: User {
+                id: row.post_author_id,
+                username: row.post_author_username,
+            },
+            resolver,
+        })
+    })
 }
 
 pub fn create_comment(
-    conn: &SqliteConnection,
+    conn: &DbConnection,
     user_id: i32,
     post_id: i32,
     body: &str,
 ) -> Result<Comment> {
-    conn.transaction(|| {
-        diesel::insert_into(comments::table)
-            .values((
-                comments::user_id.eq(user_id),
-                comments::post_id.eq(post_id),
-                comments::body.eq(body),
-            ))
-            .execute(conn)?;
+    db_run!(conn, |conn| {
+        conn.transaction(|| {
+            diesel::insert_into(comments::table)
+                .values((
+                    comments::user_id.eq(user_id),
+                    comments::post_id.eq(post_id),
+                    comments::body.eq(body),
+                ))
+                .execute(conn)?;
 
-        comments::table
-            .order(comments::id.desc())
-            .select(comments::all_columns)
-            .first(conn)
-            .map_err(Into::into)
+            bump_post_aggregates_on_comment!(conn, post_id)?;
+
+            comments::table
+                .order(comments::id.desc())
+                .select(comments::all_columns)
+                .first(conn)
+                .map_err(Into::into)
+        })
     })
 }
 
-pub fn post_comments(conn: &SqliteConnection, post_id: i32) -> Result<Vec<(Comment, User)>> {
-    comments::table
-        .filter(comments::post_id.eq(post_id))
-        .inner_join(users::table)
-        .select((comments::all_columns, (users::id, users::username)))
-        .load::<(Comment, User)>(conn)
-        .map_err(Into::into)
+/// `SortType::Active` has nothing to rank by here beyond id — unlike a
+/// listing of posts, there's no nested entity whose recency could reorder a
+/// single post's own comments — so it's treated the same as `New`.
+pub fn post_comments(
+    conn: &DbConnection,
+    post_id: i32,
+    sort: SortType,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Comment, User)>> {
+    let (limit, offset) = limit_and_offset(page, limit);
+    db_run!(conn, |conn| {
+        let query = comments::table
+            .filter(comments::post_id.eq(post_id))
+            .inner_join(users::table)
+            .select((comments::all_columns, (users::id, users::username)))
+            .into_boxed();
+
+        let query = match sort {
+            SortType::Old => query.order(comments::id.asc()),
+            SortType::New | SortType::Active => query.order(comments::id.desc()),
+        };
+
+        query
+            .limit(limit)
+            .offset(offset)
+            .load::<(Comment, User)>(conn)
+            .map_err(Into::into)
+    })
 }
 
 pub fn user_comments(
-    conn: &SqliteConnection,
+    conn: &DbConnection,
     user_id: i32,
 ) -> Result<Vec<(Comment, PostWithComment)>> {
-    comments::table
-        .filter(comments::user_id.eq(user_id))
-        .inner_join(posts::table)
-        .select((
-            comments::all_columns,
-            (posts::id, posts::title, posts::published),
-        ))
-        .load::<(Comment, PostWithComment)>(conn)
-        .map_err(Into::into)
+    db_run!(conn, |conn| {
+        comments::table
+            .filter(comments::user_id.eq(user_id))
+            .inner_join(posts::table)
+            .select((
+                comments::all_columns,
+                (posts::id, posts::title, posts::published),
+            ))
+            .load::<(Comment, PostWithComment)>(conn)
+            .map_err(Into::into)
+    })
 }
 
 pub fn all_posts_with_comments_user(
-    conn: &SqliteConnection,
+    conn: &DbConnection,
+    sort: SortType,
+    page: Option<i64>,
+    limit: Option<i64>,
 ) -> Result<Vec<((Post, User), Vec<(Comment, User)>)>> {
-    let query = posts::table
-        .order(posts::id.desc())
-        .filter(posts::published.eq(true))
-        .inner_join(users::table)
-        .select((posts::all_columns, (users::id, users::username)));
-    let posts_with_user = query.load::<(Post, User)>(conn)?;
-    // We then use the unzip method on std::iter::Iterator which turns an iterator of pairs into a pair of iterators.
-    // we turn Vec<(Post, User)> into (Vec<Post>, Vec<User>).
-    let (posts, post_users): (Vec<_>, Vec<_>) = posts_with_user.into_iter().unzip();
-    // To associate the comments into chunks indexed by the posts we use the grouped_by method provided by Diesel. Note this does not generate a GROUP BY statement in SQL rather it is just operating on the data structures in memory of already loaded data.
-    let comments = Comment::belonging_to(&posts)
-        .inner_join(users::table)
-        .select((comments::all_columns, (users::id, users::username)))
-        .load::<(Comment, User)>(conn)?
-        .grouped_by(&posts);
-    // we can use the zip method on iterator to take all of these vectors and combine them into the output format we were looking for
-    Ok(posts.into_iter().zip(post_users).zip(comments).collect())
+    let (limit, offset) = limit_and_offset(page, limit);
+    db_run!(conn, |conn| {
+        let query = posts::table
+            .filter(posts::published.eq(true))
+            .inner_join(users::table)
+            .select((posts::all_columns, (users::id, users::username)))
+            .into_boxed();
+
+        let query = match sort {
+            SortType::New => query.order(posts::id.desc()),
+            SortType::Old => query.order(posts::id.asc()),
+            SortType::Active => query.order(sql::<Integer>(ACTIVE_ORDER)),
+        };
+
+        let posts_with_user = query.limit(limit).offset(offset).load::<(Post, User)>(conn)?;
+        // We then use the unzip method on std::iter::Iterator which turns an iterator of pairs into a pair of iterators.
+        // we turn Vec<(Post, User)> into (Vec<Post>, Vec<User>).
+        let (posts, post_users): (Vec<_>, Vec<_>) = posts_with_user.into_iter().unzip();
+        // To associate the comments into chunks indexed by the posts we use the grouped_by method provided by Diesel. Note this does not generate a GROUP BY statement in SQL rather it is just operating on the data structures in memory of already loaded data.
+        let comments = Comment::belonging_to(&posts)
+            .inner_join(users::table)
+            .select((comments::all_columns, (users::id, users::username)))
+            .load::<(Comment, User)>(conn)?
+            .grouped_by(&posts);
+        // we can use the zip method on iterator to take all of these vectors and combine them into the output format we were looking for
+        Ok(posts.into_iter().zip(post_users).zip(comments).collect())
+    })
 }
 
 pub fn user_posts_with_comments(
-    conn: &SqliteConnection,
+    conn: &DbConnection,
     user_id: i32,
 ) -> Result<Vec<(Post, Vec<(Comment, User)>)>> {
-    let posts = posts::table
-        .filter(posts::user_id.eq(user_id))
-        .order(posts::id.desc())
-        .select(posts::all_columns)
-        .load::<Post>(conn)?;
-
-    let comments = Comment::belonging_to(&posts)
-        .inner_join(users::table)
-        .select((comments::all_columns, (users::id, users::username)))
-        .load::<(Comment, User)>(conn)?
-        .grouped_by(&posts);
-
-    Ok(posts.into_iter().zip(comments).collect())
+    db_run!(conn, |conn| {
+        let posts = posts::table
+            .filter(posts::user_id.eq(user_id))
+            .order(posts::id.desc())
+            .select(posts::all_columns)
+            .load::<Post>(conn)?;
+
+        let comments = Comment::belonging_to(&posts)
+            .inner_join(users::table)
+            .select((comments::all_columns, (users::id, users::username)))
+            .load::<(Comment, User)>(conn)?
+            .grouped_by(&posts);
+
+        Ok(posts.into_iter().zip(comments).collect())
+    })
+}
+
+/// Substring search over published posts' `title`/`body`, matching either
+/// field. See `like_pattern` for the wildcard-escaping rules.
+pub fn search_posts(
+    conn: &DbConnection,
+    query: &str,
+    page: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<(Post, User)>> {
+    let (limit, offset) = limit_and_offset(page, limit);
+    let pattern = like_pattern(query);
+    db_run!(conn, |conn| {
+        posts::table
+            .filter(posts::published.eq(true))
+            .filter(
+                case_insensitive_like!(posts::title, pattern.clone())
+                    .or(case_insensitive_like!(posts::body, pattern)),
+            )
+            .inner_join(users::table)
+            .select((posts::all_columns, (users::id, users::username)))
+            .order(posts::id.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<(Post, User)>(conn)
+            .map_err(Into::into)
+    })
+}
+
+/// Substring search over `users::username`. See `like_pattern` for the
+/// wildcard-escaping rules.
+pub fn search_users(conn: &DbConnection, query: &str) -> Result<Vec<User>> {
+    let pattern = like_pattern(query);
+    db_run!(conn, |conn| {
+        users::table
+            .filter(case_insensitive_like!(users::username, pattern))
+            .select((users::id, users::username))
+            .load::<User>(conn)
+            .map_err(Into::into)
+    })
 }
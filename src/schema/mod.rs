@@ -0,0 +1,12 @@
+// Each backend gets its own `table!` definitions under a distinct module
+// name so `db_run!` can bring exactly one of them into scope per connection
+// variant. Enable the matching Cargo feature(s) to compile it in.
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[cfg(feature = "mysql")]
+pub mod mysql;
@@ -0,0 +1,91 @@
+table! {
+    comments (id) {
+        id -> Integer,
+        user_id -> Integer,
+        post_id -> Integer,
+        body -> Text,
+    }
+}
+
+table! {
+    posts (id) {
+        id -> Integer,
+        user_id -> Integer,
+        title -> Text,
+        body -> Text,
+        published -> Bool,
+    }
+}
+
+table! {
+    users (id) {
+        id -> Integer,
+        username -> Text,
+    }
+}
+
+table! {
+    post_aggregates (post_id) {
+        post_id -> Integer,
+        comments -> Integer,
+        score -> Integer,
+        newest_comment_time -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    post_like (user_id, post_id) {
+        user_id -> Integer,
+        post_id -> Integer,
+        score -> SmallInt,
+    }
+}
+
+table! {
+    post_saved (user_id, post_id) {
+        user_id -> Integer,
+        post_id -> Integer,
+    }
+}
+
+table! {
+    post_read (user_id, post_id) {
+        user_id -> Integer,
+        post_id -> Integer,
+    }
+}
+
+joinable!(posts -> users (user_id));
+joinable!(comments -> users (user_id));
+joinable!(comments -> posts (post_id));
+joinable!(post_aggregates -> posts (post_id));
+joinable!(post_like -> posts (post_id));
+joinable!(post_like -> users (user_id));
+joinable!(post_saved -> posts (post_id));
+joinable!(post_saved -> users (user_id));
+joinable!(post_read -> posts (post_id));
+joinable!(post_read -> users (user_id));
+
+table! {
+    post_report (id) {
+        id -> Integer,
+        creator_id -> Integer,
+        post_id -> Integer,
+        reason -> Text,
+        resolved -> Bool,
+        resolver_id -> Nullable<Integer>,
+    }
+}
+
+joinable!(post_report -> posts (post_id));
+
+allow_tables_to_appear_in_same_query!(
+    comments,
+    posts,
+    users,
+    post_aggregates,
+    post_like,
+    post_saved,
+    post_read,
+    post_report
+);